@@ -1,31 +1,650 @@
 use std::{
     path::PathBuf,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
 use rfd::FileDialog;
-use rustube::{tokio::sync::watch, Callback, Id, Video};
+use rustube::{tokio::sync::watch, Callback, Id, Playlist, Stream, Video};
 use serde::{Deserialize, Serialize};
 use strum::EnumIter;
 use tokio::sync::watch::Sender;
 
-async fn download<'a>(url: String, path: &PathBuf, tx: Sender<f32>) {
-    let id = Id::from_raw(&url).unwrap();
+/// Picks the output filename's extension for a given download type.
+fn extension_for(download_type: DownloadType) -> &'static str {
+    match download_type {
+        DownloadType::AudioOnly => "mp3",
+        DownloadType::VideoAudio => "mp4",
+    }
+}
+
+/// Pulls the `list=` query parameter out of a YouTube URL, if present.
+fn extract_playlist_id(url: &str) -> Option<String> {
+    let query = url.split_once('?')?.1;
+    query.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        (key == "list").then(|| value.to_owned())
+    })
+}
+
+/// A progress sample sent from the download task to `update`: how far
+/// through the file we are, plus enough to show instantaneous speed and
+/// an ETA next to the progress bar.
+#[derive(Debug, Clone, Copy, Default)]
+struct Progress {
+    fraction: f32,
+    bytes_per_second: f32,
+    eta_seconds: Option<f32>,
+}
+
+/// Renders a progress bar label like `42% · 1.2 MiB/s · ETA 00:01:30`.
+fn progress_label(progress: &Progress) -> String {
+    let speed = format_byte_rate(progress.bytes_per_second);
+
+    match progress.eta_seconds {
+        Some(eta) => format!(
+            "{:.0}% · {speed} · ETA {}",
+            progress.fraction * 100.0,
+            format_duration(eta)
+        ),
+        None => format!("{:.0}% · {speed}", progress.fraction * 100.0),
+    }
+}
+
+fn format_byte_rate(bytes_per_second: f32) -> String {
+    format!("{}/s", format_bytes(bytes_per_second))
+}
+
+fn format_bytes(bytes: f32) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut value = bytes.max(0.0);
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+fn format_duration(seconds: f32) -> String {
+    let total_seconds = seconds.max(0.0) as u64;
+
+    format!(
+        "{:02}:{:02}:{:02}",
+        total_seconds / 3600,
+        (total_seconds % 3600) / 60,
+        total_seconds % 60
+    )
+}
+
+/// Shared metadata for every job that came from expanding the same
+/// playlist, so `update` can render one "N of M complete" header for
+/// the whole group instead of repeating the title on every row.
+struct PlaylistInfo {
+    title: String,
+}
+
+/// A playlist that's been resolved in the background and is waiting for
+/// `update` to turn it into queued `DownloadJob`s on the UI thread.
+struct PendingPlaylist {
+    info: Arc<PlaylistInfo>,
+    dir: PathBuf,
+    download_type: DownloadType,
+    backend: Backend,
+    video_urls: Vec<String>,
+}
+
+/// A single stream offered by a resolved video, reduced to what the UI needs
+/// to show and re-select it later (the `Stream` itself isn't `Send`-friendly
+/// to stash across frames, so we keep the `itag` and look it up again).
+#[derive(Debug, Clone)]
+struct StreamOption {
+    itag: u64,
+    label: String,
+    includes_video: bool,
+    includes_audio: bool,
+}
+
+impl StreamOption {
+    /// Whether this stream belongs in the quality dropdown for
+    /// `download_type`. `VideoAudio` only offers muxed streams, so a
+    /// video-only itag can't be picked by hand. `select_stream`'s auto path
+    /// (no quality chosen) can still end up with a video-only stream when the
+    /// video has no muxed stream at all.
+    fn matches(&self, download_type: DownloadType) -> bool {
+        match download_type {
+            DownloadType::AudioOnly => self.includes_audio && !self.includes_video,
+            DownloadType::VideoAudio => self.includes_video && self.includes_audio,
+        }
+    }
+}
+
+fn describe_stream(stream: &Stream) -> StreamOption {
+    let label = match (&stream.quality_label, &stream.audio_quality) {
+        (Some(quality_label), Some(audio_quality)) => {
+            format!("{quality_label} + {audio_quality:?}")
+        }
+        (Some(quality_label), None) => quality_label.to_string(),
+        (None, Some(audio_quality)) => format!("{audio_quality:?}"),
+        (None, None) => stream.quality.to_string(),
+    };
+
+    StreamOption {
+        itag: stream.itag,
+        label,
+        includes_video: stream.includes_video_track,
+        includes_audio: stream.includes_audio_track,
+    }
+}
 
-    let callback = Callback::new().connect_on_progress_closure(move |x| {
-        let percentage = x.current_chunk as f32 / x.content_length.unwrap() as f32;
-        tx.send(percentage).expect("Failed to send");
-        // progress(percentage, 20)
-    });
+/// The outcome of a failed download attempt, split into whether it's worth
+/// showing a "Retry" button for. Invalid URLs and unavailable videos won't
+/// fix themselves on retry; network hiccups and transient API errors might.
+#[derive(Debug, Clone)]
+enum DownloadError {
+    Retryable(String),
+    Fatal(String),
+    /// `rustube` couldn't decipher YouTube's signature. Never shown to the
+    /// user directly: `download` catches it and retries with yt-dlp.
+    SignatureFailure(String),
+}
+
+impl DownloadError {
+    fn message(&self) -> &str {
+        match self {
+            Self::Retryable(message) | Self::Fatal(message) | Self::SignatureFailure(message) => {
+                message
+            }
+        }
+    }
+
+    fn is_retryable(&self) -> bool {
+        matches!(self, Self::Retryable(_))
+    }
+}
+
+/// Picks the stream a given `itag` refers to out of a freshly resolved
+/// `Video`, preferring a muxed (combined) stream for `VideoAudio` and
+/// falling back to the highest-quality stream with a video track (which may
+/// have no audio track) if none is muxed.
+fn select_stream(
+    video: &Video,
+    download_type: DownloadType,
+    itag: Option<u64>,
+) -> Result<Stream, DownloadError> {
+    if let Some(itag) = itag {
+        if let Some(stream) = video.streams().iter().find(|s| s.itag == itag) {
+            return Ok(stream.clone());
+        }
+    }
+
+    match download_type {
+        DownloadType::AudioOnly => video
+            .best_audio()
+            .cloned()
+            .ok_or_else(|| DownloadError::Fatal("No audio stream available".to_owned())),
+        DownloadType::VideoAudio => video
+            .streams()
+            .iter()
+            .find(|s| s.includes_video_track && s.includes_audio_track)
+            .or_else(|| {
+                video
+                    .streams()
+                    .iter()
+                    .filter(|s| s.includes_video_track)
+                    .max_by_key(|s| s.quality)
+            })
+            .cloned()
+            .ok_or_else(|| DownloadError::Fatal("No video stream available".to_owned())),
+    }
+}
+
+/// Longest filename we'll suggest, leaving headroom below typical
+/// filesystem limits once the extension is appended.
+const MAX_FILENAME_LEN: usize = 100;
+
+/// Turns a video title into a name that's safe to use as a filename across
+/// platforms: path separators, control characters and reserved characters
+/// are replaced, trailing dots/spaces (which Windows rejects) are trimmed,
+/// and the result is capped to a sane length.
+fn sanitize_filename(title: &str) -> String {
+    let mut sanitized: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .take(MAX_FILENAME_LEN)
+        .collect();
+
+    while matches!(sanitized.chars().last(), Some('.') | Some(' ')) {
+        sanitized.pop();
+    }
+
+    if sanitized.is_empty() {
+        sanitized = "video".to_owned();
+    }
+
+    sanitized
+}
+
+/// Which extractor actually performs the download. `Rustube` is pure-Rust
+/// and fast, but breaks whenever YouTube rotates its signature cipher;
+/// `YtDlp` shells out to the external binary, which lags YouTube changes
+/// less but needs to be installed separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter, Deserialize, Serialize)]
+pub enum Backend {
+    #[default]
+    Rustube,
+    YtDlp,
+}
+
+impl ToString for Backend {
+    fn to_string(&self) -> String {
+        let string = match self {
+            Self::Rustube => "Rustube",
+            Self::YtDlp => "yt-dlp",
+        };
+
+        string.to_string()
+    }
+}
 
-    Video::from_id(id.into_owned())
+/// Settings for the `YtDlp` backend, persisted alongside the rest of
+/// `App` so they don't need re-entering every launch.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct YtDlpConfig {
+    executable: String,
+    extra_args: String,
+    working_dir: Option<PathBuf>,
+}
+
+impl Default for YtDlpConfig {
+    fn default() -> Self {
+        Self {
+            executable: "yt-dlp".to_owned(),
+            extra_args: String::new(),
+            working_dir: None,
+        }
+    }
+}
+
+/// Downloads `url` via `rustube`, falling back to `classify_rustube_error`
+/// to decide whether the failure is worth retrying or reporting as-is.
+///
+/// Unlike `Stream::download_to_with_callback`, this issues a ranged
+/// request starting at whatever `path` already contains, so a job that
+/// was interrupted (app closed, connection dropped) resumes instead of
+/// re-downloading from byte zero.
+async fn download_rustube(
+    url: String,
+    path: &PathBuf,
+    download_type: DownloadType,
+    itag: Option<u64>,
+    bytes_downloaded: Arc<AtomicU64>,
+    tx: Sender<Progress>,
+) -> Result<(), DownloadError> {
+    let id =
+        Id::from_raw(&url).map_err(|_| DownloadError::Fatal("Invalid YouTube URL".to_owned()))?;
+
+    let video = Video::from_id(id.into_owned())
+        .await
+        .map_err(|err| classify_rustube_error(&err))?;
+
+    let stream = select_stream(&video, download_type, itag)?;
+    let content_length = stream.content_length;
+
+    let start_byte = tokio::fs::metadata(path)
         .await
-        .unwrap()
-        .best_audio()
-        .unwrap()
-        .download_to_with_callback(path, callback)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0)
+        .min(content_length.unwrap_or(u64::MAX));
+    bytes_downloaded.store(start_byte, Ordering::Relaxed);
+
+    download_ranged(
+        &stream.download_url,
+        path,
+        start_byte,
+        content_length,
+        bytes_downloaded,
+        tx,
+    )
+    .await
+}
+
+/// Fetches `url` with an HTTP `Range` request starting at `start_byte`,
+/// appending the response body to `path` and periodically reporting
+/// `Progress` (fraction, instantaneous throughput, ETA) over `tx`.
+async fn download_ranged(
+    url: &str,
+    path: &PathBuf,
+    start_byte: u64,
+    total_length: Option<u64>,
+    bytes_downloaded: Arc<AtomicU64>,
+    tx: Sender<Progress>,
+) -> Result<(), DownloadError> {
+    use tokio::{
+        fs::OpenOptions,
+        io::{AsyncSeekExt, AsyncWriteExt},
+    };
+
+    if total_length.is_some_and(|len| start_byte >= len) {
+        // Already have the whole file from a previous run; an unsatisfiable
+        // `Range` request would otherwise get us a 416 body written past EOF.
+        return Ok(());
+    }
+
+    let mut request = reqwest::Client::new().get(url);
+    if start_byte > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={start_byte}-"));
+    }
+
+    let mut response = request
+        .send()
+        .await
+        .map_err(|err| DownloadError::Retryable(format!("Network error: {err}")))?;
+
+    // A server is free to ignore `Range` and answer with a 200 carrying the
+    // full body; if it does, restart the file from byte zero instead of
+    // appending the whole thing after what we already had on disk.
+    let start_byte = if start_byte > 0 && response.status() != reqwest::StatusCode::PARTIAL_CONTENT
+    {
+        bytes_downloaded.store(0, Ordering::Relaxed);
+        0
+    } else {
+        start_byte
+    };
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(start_byte == 0)
+        .open(path)
+        .await
+        .map_err(|err| DownloadError::Fatal(format!("Failed to open output file: {err}")))?;
+    file.seek(std::io::SeekFrom::Start(start_byte))
+        .await
+        .map_err(|err| DownloadError::Fatal(format!("Failed to seek output file: {err}")))?;
+
+    let mut last_sample_at = Instant::now();
+    let mut last_sample_bytes = start_byte;
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|err| DownloadError::Retryable(format!("Network error: {err}")))?
+    {
+        file.write_all(&chunk)
+            .await
+            .map_err(|err| DownloadError::Fatal(format!("Failed to write output file: {err}")))?;
+
+        let downloaded =
+            bytes_downloaded.fetch_add(chunk.len() as u64, Ordering::Relaxed) + chunk.len() as u64;
+
+        let elapsed = last_sample_at.elapsed();
+        if elapsed >= Duration::from_millis(500) {
+            let bytes_per_second =
+                (downloaded - last_sample_bytes) as f32 / elapsed.as_secs_f32();
+            let fraction = total_length
+                .map(|len| downloaded as f32 / len.max(1) as f32)
+                .unwrap_or(0.0);
+            let eta_seconds = total_length.and_then(|len| {
+                (bytes_per_second > 0.0)
+                    .then(|| len.saturating_sub(downloaded) as f32 / bytes_per_second)
+            });
+
+            let _ = tx.send(Progress {
+                fraction,
+                bytes_per_second,
+                eta_seconds,
+            });
+
+            last_sample_at = Instant::now();
+            last_sample_bytes = downloaded;
+        }
+    }
+
+    Ok(())
+}
+
+/// `rustube` surfaces signature/cipher failures, unavailable videos and
+/// ordinary network errors all as the same opaque error type, so we fall
+/// back to string-sniffing the message to tell them apart: a cipher change
+/// is worth a silent yt-dlp fallback, an unavailable video is never going to
+/// succeed on retry, and everything else is assumed to be a transient
+/// network/API hiccup.
+fn classify_rustube_error(err: &rustube::Error) -> DownloadError {
+    let message = err.to_string();
+    let lower = message.to_lowercase();
+
+    if lower.contains("cipher") || lower.contains("signature") || lower.contains("decipher") {
+        DownloadError::SignatureFailure(message)
+    } else if lower.contains("unavailable")
+        || lower.contains("not found")
+        || lower.contains("private")
+        || lower.contains("removed")
+        || lower.contains("age restrict")
+    {
+        DownloadError::Fatal("Video unavailable".to_owned())
+    } else {
+        DownloadError::Retryable(message)
+    }
+}
+
+/// Parses a human-readable size like `10.52MiB` into a byte count.
+fn parse_byte_size(text: &str) -> Option<f32> {
+    let split_at = text.find(|c: char| c.is_alphabetic())?;
+    let (value, unit) = text.split_at(split_at);
+    let value: f32 = value.trim().parse().ok()?;
+
+    let multiplier = match unit {
+        "B" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+
+    Some(value * multiplier)
+}
+
+/// Parses a yt-dlp ETA like `00:05` or `01:02:03` into seconds.
+fn parse_yt_dlp_eta(text: &str) -> Option<f32> {
+    text.split(':')
+        .try_fold(0f32, |acc, part| Some(acc * 60.0 + part.parse::<f32>().ok()?))
+}
+
+/// Parses a yt-dlp progress line, e.g.
+/// `[download]  45.2% of   10.52MiB at    1.20MiB/s ETA 00:05`, into the
+/// fraction complete, the total size (if known) and a `Progress` sample.
+/// Returns `None` for any other line (yt-dlp logs plenty of those).
+fn parse_yt_dlp_progress(line: &str) -> Option<(Progress, Option<f32>)> {
+    let rest = line.trim().strip_prefix("[download]")?.trim_start();
+    let fraction = (rest.split('%').next()?.trim().parse::<f32>().ok()? / 100.0).clamp(0.0, 1.0);
+
+    let total_bytes = rest
+        .split("of")
+        .nth(1)
+        .and_then(|after| after.split_whitespace().next())
+        .and_then(parse_byte_size);
+
+    let bytes_per_second = rest
+        .split("at")
+        .nth(1)
+        .and_then(|after| after.split_whitespace().next())
+        .and_then(|text| text.strip_suffix("/s").map(ToOwned::to_owned))
+        .as_deref()
+        .and_then(parse_byte_size)
+        .unwrap_or(0.0);
+
+    let eta_seconds = rest
+        .split("ETA")
+        .nth(1)
+        .and_then(|after| after.split_whitespace().next())
+        .and_then(parse_yt_dlp_eta);
+
+    Some((
+        Progress {
+            fraction,
+            bytes_per_second,
+            eta_seconds,
+        },
+        total_bytes,
+    ))
+}
+
+/// Downloads `url` by shelling out to the configured yt-dlp binary, which
+/// resumes partially-written files itself via `--continue`. Drives `tx`
+/// and `bytes_downloaded` from its `[download] xx.x%` progress lines.
+async fn download_yt_dlp(
+    url: String,
+    path: &PathBuf,
+    download_type: DownloadType,
+    config: &YtDlpConfig,
+    bytes_downloaded: Arc<AtomicU64>,
+    tx: Sender<Progress>,
+) -> Result<(), DownloadError> {
+    use tokio::{
+        io::{AsyncBufReadExt, BufReader},
+        process::Command,
+    };
+
+    let mut command = Command::new(&config.executable);
+
+    if let Some(working_dir) = &config.working_dir {
+        command.current_dir(working_dir);
+    }
+
+    // yt-dlp rewrites its `[download] xx.x%` line in place with carriage
+    // returns by default; `--newline` makes it emit one per line instead so
+    // `BufReader::lines()` (which splits on `\n`) actually sees each sample.
+    command.arg("--newline");
+
+    if !config.extra_args.trim().is_empty() {
+        command.args(config.extra_args.split_whitespace());
+    }
+
+    match download_type {
+        DownloadType::AudioOnly => {
+            command.args(["-x", "--audio-format", "mp3"]);
+        }
+        DownloadType::VideoAudio => {
+            command.args(["-f", "best"]);
+        }
+    }
+
+    let mut child = command
+        .arg("--continue")
+        .arg("-o")
+        .arg(path)
+        .arg(&url)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(|err| DownloadError::Fatal(format!("Failed to launch yt-dlp: {err}")))?;
+
+    let stdout = child
+        .stdout
+        .take()
+        .expect("yt-dlp was spawned with piped stdout");
+    let mut lines = BufReader::new(stdout).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if let Some((progress, total_bytes)) = parse_yt_dlp_progress(&line) {
+            if let Some(total_bytes) = total_bytes {
+                bytes_downloaded.store((progress.fraction * total_bytes) as u64, Ordering::Relaxed);
+            }
+            let _ = tx.send(progress);
+        }
+    }
+
+    let status = child
+        .wait()
         .await
-        .unwrap();
+        .map_err(|err| DownloadError::Retryable(format!("yt-dlp exited unexpectedly: {err}")))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(DownloadError::Retryable(
+            "yt-dlp reported a failure".to_owned(),
+        ))
+    }
+}
+
+/// Downloads `url` with the selected `backend`, automatically falling
+/// back to yt-dlp when `rustube` fails because YouTube's signature
+/// cipher changed underneath it.
+async fn download(
+    url: String,
+    path: &PathBuf,
+    download_type: DownloadType,
+    itag: Option<u64>,
+    backend: Backend,
+    yt_dlp_config: YtDlpConfig,
+    bytes_downloaded: Arc<AtomicU64>,
+    tx: Sender<Progress>,
+) -> Result<(), DownloadError> {
+    match backend {
+        Backend::YtDlp => {
+            download_yt_dlp(url, path, download_type, &yt_dlp_config, bytes_downloaded, tx).await
+        }
+        Backend::Rustube => {
+            match download_rustube(
+                url.clone(),
+                path,
+                download_type,
+                itag,
+                Arc::clone(&bytes_downloaded),
+                tx.clone(),
+            )
+            .await
+            {
+                Err(DownloadError::SignatureFailure(_)) => {
+                    download_yt_dlp(url, path, download_type, &yt_dlp_config, bytes_downloaded, tx)
+                        .await
+                }
+                other => other,
+            }
+        }
+    }
+}
+
+/// A single queued/in-progress/completed download, owned by `App::jobs`.
+///
+/// Each job tracks its own progress and state so several downloads can run
+/// side-by-side without clobbering one another.
+pub struct DownloadJob {
+    id: u64,
+    url: String,
+    target: PathBuf,
+    download_type: DownloadType,
+    itag: Option<u64>,
+    backend: Backend,
+    state: AppState,
+    progress: Progress,
+    bytes_downloaded: Arc<AtomicU64>,
+    playlist: Option<Arc<PlaylistInfo>>,
+}
+
+/// A snapshot of an in-flight or failed `DownloadJob`, persisted so it can
+/// be resumed (via the existing "Retry" flow) after the app is relaunched.
+/// Finished jobs aren't worth snapshotting, so they're dropped on save.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct PersistedJob {
+    url: String,
+    target: PathBuf,
+    download_type: DownloadType,
+    itag: Option<u64>,
+    backend: Backend,
+    bytes_downloaded: u64,
 }
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
@@ -34,12 +653,47 @@ async fn download<'a>(url: String, path: &PathBuf, tx: Sender<f32>) {
 pub struct App {
     url: String,
     download_type: DownloadType,
+    quality: Option<u64>,
+    backend: Backend,
+    yt_dlp: YtDlpConfig,
+
+    /// Snapshot of unfinished jobs, refreshed in `save` and consumed in
+    /// `new` to rebuild `jobs` across a relaunch.
+    persisted_jobs: Vec<PersistedJob>,
+
+    #[serde(skip)]
+    jobs: Vec<Arc<Mutex<DownloadJob>>>,
 
     #[serde(skip)]
-    state: Arc<Mutex<AppState>>,
+    next_job_id: u64,
 
     #[serde(skip)]
-    value: Arc<Mutex<f32>>,
+    available_streams: Arc<Mutex<Vec<StreamOption>>>,
+
+    #[serde(skip)]
+    title: Arc<Mutex<Option<String>>>,
+
+    /// Set by `fetch_streams` when resolving the video fails, so `update`
+    /// has something to show instead of leaving the quality dropdown
+    /// silently empty.
+    #[serde(skip)]
+    stream_fetch_error: Arc<Mutex<Option<String>>>,
+
+    #[serde(skip)]
+    pending_playlist: Arc<Mutex<Option<PendingPlaylist>>>,
+
+    /// Set by `fetch_playlist` when resolving the playlist fails, so
+    /// `update` has something to show instead of silently queuing nothing.
+    #[serde(skip)]
+    playlist_error: Arc<Mutex<Option<String>>>,
+
+    /// The URL "Download" was clicked for while `title` wasn't cached yet,
+    /// so the title can still be resolved before the save dialog opens
+    /// instead of falling back to a generic filename. Cleared once
+    /// `maybe_prompt_pending_download` acts on it (or the URL changes out
+    /// from under it).
+    #[serde(skip)]
+    pending_download_url: Option<String>,
 }
 
 impl Default for App {
@@ -47,8 +701,18 @@ impl Default for App {
         Self {
             url: "".to_owned(),
             download_type: DownloadType::AudioOnly,
-            value: Arc::new(Mutex::new(0.0)),
-            state: Arc::new(Mutex::new(AppState::Initial)),
+            quality: None,
+            backend: Backend::Rustube,
+            yt_dlp: YtDlpConfig::default(),
+            persisted_jobs: Vec::new(),
+            jobs: Vec::new(),
+            next_job_id: 0,
+            available_streams: Arc::new(Mutex::new(Vec::new())),
+            title: Arc::new(Mutex::new(None)),
+            stream_fetch_error: Arc::new(Mutex::new(None)),
+            pending_playlist: Arc::new(Mutex::new(None)),
+            playlist_error: Arc::new(Mutex::new(None)),
+            pending_download_url: None,
         }
     }
 }
@@ -58,11 +722,289 @@ impl App {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        let mut app: App = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+
+        // Turn last session's unfinished jobs back into a visible, resumable
+        // queue instead of silently dropping them.
+        for persisted in std::mem::take(&mut app.persisted_jobs) {
+            let id = app.next_job_id;
+            app.next_job_id += 1;
+
+            let job = Arc::new(Mutex::new(DownloadJob {
+                id,
+                url: persisted.url,
+                target: persisted.target,
+                download_type: persisted.download_type,
+                itag: persisted.itag,
+                backend: persisted.backend,
+                state: AppState::Failed {
+                    message: "Interrupted before finishing - click Retry to resume".to_owned(),
+                    retryable: true,
+                },
+                progress: Progress::default(),
+                bytes_downloaded: Arc::new(AtomicU64::new(persisted.bytes_downloaded)),
+                playlist: None,
+            }));
+            app.jobs.push(job);
         }
 
-        Default::default()
+        app
+    }
+
+    /// Resolves `self.url` in the background and populates
+    /// `available_streams` and `title` so the quality `ComboBox` and the
+    /// save dialog's suggested filename have something to show.
+    fn fetch_streams(&mut self, ctx: &egui::Context) {
+        self.quality = None;
+        *self.available_streams.lock().unwrap() = Vec::new();
+        *self.title.lock().unwrap() = None;
+        *self.stream_fetch_error.lock().unwrap() = None;
+
+        let url = self.url.clone();
+        let streams = Arc::clone(&self.available_streams);
+        let title = Arc::clone(&self.title);
+        let error = Arc::clone(&self.stream_fetch_error);
+        let ctx = ctx.clone();
+
+        tokio::spawn(async move {
+            let id = match Id::from_raw(&url) {
+                Ok(id) => id,
+                Err(_) => {
+                    *error.lock().unwrap() = Some("Invalid YouTube URL".to_owned());
+                    ctx.request_repaint();
+                    return;
+                }
+            };
+
+            let video = match Video::from_id(id.into_owned()).await {
+                Ok(video) => video,
+                Err(err) => {
+                    *error.lock().unwrap() = Some(format!("Failed to fetch video info: {err}"));
+                    ctx.request_repaint();
+                    return;
+                }
+            };
+
+            let options = video.streams().iter().map(describe_stream).collect();
+
+            *streams.lock().unwrap() = options;
+            *title.lock().unwrap() = Some(video.video_details().title.clone());
+            ctx.request_repaint();
+        });
+    }
+
+    /// Shows the save dialog, suggesting a filename from `self.title` when
+    /// it's cached, and queues the download if the user confirms.
+    fn prompt_and_queue_download(&mut self, ctx: &egui::Context) {
+        let extension = extension_for(self.download_type);
+
+        let file_name = match self.title.lock().unwrap().as_deref() {
+            Some(title) => format!("{}.{extension}", sanitize_filename(title)),
+            None => format!("soundtrack.{extension}"),
+        };
+
+        let file = FileDialog::new()
+            .add_filter(extension, &[extension])
+            .set_file_name(file_name)
+            .save_file();
+
+        if let Some(path) = file {
+            let url = std::mem::take(&mut self.url);
+            self.queue_download(url, path, ctx);
+        }
+    }
+
+    /// Picks up after `fetch_streams` resolves the title for a download
+    /// that was requested before it was cached (the default "paste URL →
+    /// Download" path, as opposed to a manual "Fetch qualities" click), and
+    /// opens the save dialog now that a proper filename can be suggested.
+    fn maybe_prompt_pending_download(&mut self, ctx: &egui::Context) {
+        let Some(pending_url) = &self.pending_download_url else {
+            return;
+        };
+
+        if *pending_url != self.url {
+            // The URL changed while we were resolving the old one.
+            self.pending_download_url = None;
+            return;
+        }
+
+        let resolved =
+            self.title.lock().unwrap().is_some() || self.stream_fetch_error.lock().unwrap().is_some();
+        if !resolved {
+            return;
+        }
+
+        self.pending_download_url = None;
+        self.prompt_and_queue_download(ctx);
+    }
+
+    /// Queues `url` for download to `target` and spawns the background task
+    /// that drives it.
+    fn queue_download(&mut self, url: String, target: PathBuf, ctx: &egui::Context) {
+        let id = self.next_job_id;
+        self.next_job_id += 1;
+
+        let job = Arc::new(Mutex::new(DownloadJob {
+            id,
+            url,
+            target,
+            download_type: self.download_type,
+            itag: self.quality,
+            backend: self.backend,
+            state: AppState::Downloading,
+            progress: Progress::default(),
+            bytes_downloaded: Arc::new(AtomicU64::new(0)),
+            playlist: None,
+        }));
+        self.jobs.push(Arc::clone(&job));
+
+        self.start_job(job, ctx);
+    }
+
+    /// Resolves a playlist in the background and stashes the result in
+    /// `pending_playlist` for `drain_pending_playlist` to pick up, since
+    /// queuing jobs mutates `self` and can't happen from inside the task.
+    fn fetch_playlist(&mut self, playlist_id: String, dir: PathBuf, ctx: &egui::Context) {
+        let download_type = self.download_type;
+        let backend = self.backend;
+        let pending = Arc::clone(&self.pending_playlist);
+        let error = Arc::clone(&self.playlist_error);
+        *error.lock().unwrap() = None;
+        let ctx = ctx.clone();
+
+        tokio::spawn(async move {
+            let id = match Id::from_raw(&playlist_id) {
+                Ok(id) => id,
+                Err(_) => {
+                    *error.lock().unwrap() = Some("Invalid playlist URL".to_owned());
+                    ctx.request_repaint();
+                    return;
+                }
+            };
+
+            let playlist = match Playlist::from_id(id.into_owned()).await {
+                Ok(playlist) => playlist,
+                Err(err) => {
+                    *error.lock().unwrap() = Some(format!("Failed to fetch playlist: {err}"));
+                    ctx.request_repaint();
+                    return;
+                }
+            };
+
+            let info = Arc::new(PlaylistInfo {
+                title: playlist.title().to_owned(),
+            });
+
+            let video_urls = playlist
+                .videos()
+                .iter()
+                .map(|video| format!("https://www.youtube.com/watch?v={}", video.id()))
+                .collect();
+
+            *pending.lock().unwrap() = Some(PendingPlaylist {
+                info,
+                dir,
+                download_type,
+                backend,
+                video_urls,
+            });
+            ctx.request_repaint();
+        });
+    }
+
+    /// Turns a playlist resolved by `fetch_playlist` into one queued
+    /// `DownloadJob` per video, tagged with the shared `PlaylistInfo` so
+    /// `update` can render them as a group.
+    fn drain_pending_playlist(&mut self, ctx: &egui::Context) {
+        let Some(pending) = self.pending_playlist.lock().unwrap().take() else {
+            return;
+        };
+
+        let extension = extension_for(pending.download_type);
+
+        for (index, url) in pending.video_urls.into_iter().enumerate() {
+            let id = self.next_job_id;
+            self.next_job_id += 1;
+
+            let target = pending
+                .dir
+                .join(format!("{:03}.{extension}", index + 1));
+
+            let job = Arc::new(Mutex::new(DownloadJob {
+                id,
+                url,
+                target,
+                download_type: pending.download_type,
+                itag: None,
+                backend: pending.backend,
+                state: AppState::Downloading,
+                progress: Progress::default(),
+                bytes_downloaded: Arc::new(AtomicU64::new(0)),
+                playlist: Some(Arc::clone(&pending.info)),
+            }));
+            self.jobs.push(Arc::clone(&job));
+
+            self.start_job(job, ctx);
+        }
+    }
+
+    /// Spawns the background tasks that drive `job`: one that runs the
+    /// download and records its outcome, and one that forwards progress
+    /// updates into the job so `update` can render them. Used both for
+    /// freshly queued jobs and for retrying a failed one.
+    fn start_job(&self, job: Arc<Mutex<DownloadJob>>, ctx: &egui::Context) {
+        let (url, target, download_type, itag, backend, bytes_downloaded) = {
+            let mut job = job.lock().unwrap();
+            job.state = AppState::Downloading;
+            (
+                job.url.clone(),
+                job.target.clone(),
+                job.download_type,
+                job.itag,
+                job.backend,
+                Arc::clone(&job.bytes_downloaded),
+            )
+        };
+        let yt_dlp_config = self.yt_dlp.clone();
+
+        let (tx, mut rx) = watch::channel(Progress::default());
+        let ctx = ctx.clone();
+
+        // Download task
+        let job_for_download = Arc::clone(&job);
+        tokio::spawn(async move {
+            let outcome = download(
+                url,
+                &target,
+                download_type,
+                itag,
+                backend,
+                yt_dlp_config,
+                bytes_downloaded,
+                tx,
+            )
+            .await;
+            let mut job = job_for_download.lock().unwrap();
+            job.state = match outcome {
+                Ok(()) => AppState::Done,
+                Err(err) => AppState::Failed {
+                    message: err.message().to_owned(),
+                    retryable: err.is_retryable(),
+                },
+            };
+        });
+
+        // Progress-forwarding task
+        tokio::spawn(async move {
+            while rx.changed().await.is_ok() {
+                job.lock().unwrap().progress = *rx.borrow();
+                ctx.request_repaint();
+            }
+        });
     }
 }
 
@@ -73,12 +1015,16 @@ pub enum DownloadType {
     VideoAudio,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default)]
 pub enum AppState {
     #[default]
     Initial,
     Downloading,
     Done,
+    Failed {
+        message: String,
+        retryable: bool,
+    },
 }
 
 impl ToString for DownloadType {
@@ -94,100 +1040,227 @@ impl ToString for DownloadType {
 
 impl eframe::App for App {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.persisted_jobs = self
+            .jobs
+            .iter()
+            .filter_map(|job| {
+                let job = job.lock().unwrap();
+                (!matches!(job.state, AppState::Done)).then(|| PersistedJob {
+                    url: job.url.clone(),
+                    target: job.target.clone(),
+                    download_type: job.download_type,
+                    itag: job.itag,
+                    backend: job.backend,
+                    bytes_downloaded: job.bytes_downloaded.load(Ordering::Relaxed),
+                })
+            })
+            .collect();
+
         eframe::set_value(storage, eframe::APP_KEY, self);
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        let Self {
-            url, value, state, ..
-        } = self;
+        self.drain_pending_playlist(ctx);
+        self.maybe_prompt_pending_download(ctx);
 
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.label("Youtube URL");
-            let state_val: AppState;
-            {
-                state_val = state.lock().unwrap().clone();
+            if ui.add(egui::TextEdit::singleline(&mut self.url)).changed() {
+                self.quality = None;
+                *self.available_streams.lock().unwrap() = Vec::new();
+                *self.title.lock().unwrap() = None;
+                *self.stream_fetch_error.lock().unwrap() = None;
+                *self.playlist_error.lock().unwrap() = None;
             }
 
             if ui
-                .add_enabled(
-                    !matches!(state_val, AppState::Downloading),
-                    egui::TextEdit::singleline(url),
-                )
-                .changed()
+                .add_enabled(!self.url.is_empty(), egui::Button::new("Fetch qualities"))
+                .clicked()
             {
-                *state.lock().unwrap() = AppState::Initial;
+                self.fetch_streams(ctx);
+            }
+
+            if let Some(message) = self.stream_fetch_error.lock().unwrap().clone() {
+                ui.colored_label(egui::Color32::RED, message);
             }
 
-            // ui.add_enabled_ui(!matches!(state_val, AppState::Downloading), |ui| {
-            //     egui::ComboBox::from_label("label")
-            //         .selected_text(self.download_type.to_string())
-            //         .show_ui(ui, |ui| {
-            //             DownloadType::iter().for_each(|t| {
-            //                 if ui
-            //                     .selectable_value(&mut self.download_type, t, t.to_string())
-            //                     .clicked()
-            //                 {
-            //                     *state.lock().unwrap() = AppState::Initial;
-            //                 }
-            //             });
-            //         })
-            // });
+            egui::ComboBox::from_label("Type")
+                .selected_text(self.download_type.to_string())
+                .show_ui(ui, |ui| {
+                    use strum::IntoEnumIterator;
+
+                    DownloadType::iter().for_each(|t| {
+                        if ui
+                            .selectable_value(&mut self.download_type, t, t.to_string())
+                            .clicked()
+                        {
+                            self.quality = None;
+                        }
+                    });
+                });
+
+            let streams = self.available_streams.lock().unwrap().clone();
+            let matching: Vec<StreamOption> = streams
+                .into_iter()
+                .filter(|s| s.matches(self.download_type))
+                .collect();
+
+            let selected_label = self
+                .quality
+                .and_then(|itag| matching.iter().find(|s| s.itag == itag))
+                .map(|s| s.label.clone())
+                .unwrap_or_else(|| "Select quality".to_owned());
+
+            egui::ComboBox::from_label("Quality")
+                .selected_text(selected_label)
+                .show_ui(ui, |ui| {
+                    for option in &matching {
+                        ui.selectable_value(&mut self.quality, Some(option.itag), &option.label);
+                    }
+                });
+
+            egui::ComboBox::from_label("Backend")
+                .selected_text(self.backend.to_string())
+                .show_ui(ui, |ui| {
+                    use strum::IntoEnumIterator;
+
+                    Backend::iter().for_each(|b| {
+                        ui.selectable_value(&mut self.backend, b, b.to_string());
+                    });
+                });
+
+            if matches!(self.backend, Backend::YtDlp) {
+                ui.horizontal(|ui| {
+                    ui.label("yt-dlp executable");
+                    ui.text_edit_singleline(&mut self.yt_dlp.executable);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Extra args");
+                    ui.text_edit_singleline(&mut self.yt_dlp.extra_args);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Working directory");
+                    let dir_label = self
+                        .yt_dlp
+                        .working_dir
+                        .as_ref()
+                        .map(|dir| dir.display().to_string())
+                        .unwrap_or_else(|| "(default)".to_owned());
+                    ui.label(dir_label);
+
+                    if ui.button("Browse").clicked() {
+                        if let Some(dir) = FileDialog::new().pick_folder() {
+                            self.yt_dlp.working_dir = Some(dir);
+                        }
+                    }
+                });
+            }
 
             if ui
-                .add_enabled(
-                    !matches!(state_val, AppState::Downloading),
-                    egui::Button::new("Download"),
-                )
+                .add_enabled(!self.url.is_empty(), egui::Button::new("Download"))
                 .clicked()
             {
-                let file = FileDialog::new()
-                    .add_filter("mp3", &["mp3"])
-                    .set_file_name("soundtrack.mp3")
-                    .save_file();
-
-                if let Some(path) = file {
-                    {
-                        *state.lock().unwrap() = AppState::Downloading;
+                if let Some(playlist_id) = extract_playlist_id(&self.url) {
+                    if let Some(dir) = FileDialog::new().pick_folder() {
+                        self.fetch_playlist(playlist_id, dir, ctx);
+                        self.url.clear();
                     }
+                } else if self.title.lock().unwrap().is_some() {
+                    self.prompt_and_queue_download(ctx);
+                } else {
+                    // Title not cached yet (the user didn't click "Fetch
+                    // qualities" first) - resolve it now so the save dialog
+                    // still gets to suggest a real filename, and pick this
+                    // back up in `maybe_prompt_pending_download` once it's in.
+                    self.pending_download_url = Some(self.url.clone());
+                    self.fetch_streams(ctx);
+                }
+            }
 
-                    let (tx, mut rx) = watch::channel(0.0);
-                    let value_arc = Arc::clone(value);
-                    let ctx = ctx.clone();
-                    let url = url.clone();
+            if let Some(message) = self.playlist_error.lock().unwrap().clone() {
+                ui.colored_label(egui::Color32::RED, message);
+            }
 
-                    let state_mutex = Arc::clone(state);
+            ui.separator();
 
-                    // Download thread
-                    tokio::spawn(async move {
-                        download(url, &path, tx).await;
-                        *state_mutex.lock().unwrap() = AppState::Done;
-                    });
+            let mut jobs_to_retry = Vec::new();
+
+            // Snapshot each job's playlist membership and completion before
+            // the render loop below locks them one at a time: counting done
+            // vs. total by re-locking `self.jobs` from inside that loop would
+            // try to lock the very job already held for the current row,
+            // and `Mutex` isn't reentrant.
+            let job_snapshots: Vec<(Option<*const PlaylistInfo>, bool)> = self
+                .jobs
+                .iter()
+                .map(|j| {
+                    let j = j.lock().unwrap();
+                    (
+                        j.playlist.as_ref().map(Arc::as_ptr),
+                        matches!(j.state, AppState::Done),
+                    )
+                })
+                .collect();
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                let mut rendered_playlists: Vec<*const PlaylistInfo> = Vec::new();
+
+                for job_arc in &self.jobs {
+                    let mut job = job_arc.lock().unwrap();
 
-                    // Handle callback thread
-                    tokio::spawn(async move {
-                        while rx.changed().await.is_ok() {
-                            *value_arc.lock().unwrap() = *rx.borrow();
-                            ctx.request_repaint();
+                    if let Some(playlist) = &job.playlist {
+                        let ptr = Arc::as_ptr(playlist);
+
+                        if !rendered_playlists.contains(&ptr) {
+                            rendered_playlists.push(ptr);
+
+                            let total = job_snapshots
+                                .iter()
+                                .filter(|(playlist_ptr, _)| *playlist_ptr == Some(ptr))
+                                .count();
+                            let done = job_snapshots
+                                .iter()
+                                .filter(|(playlist_ptr, is_done)| {
+                                    *playlist_ptr == Some(ptr) && *is_done
+                                })
+                                .count();
+
+                            ui.heading(&playlist.title);
+                            ui.label(format!("{done} of {total} complete"));
                         }
+                    }
+
+                    ui.group(|ui| {
+                        ui.label(&job.url);
+
+                        match &job.state {
+                            AppState::Done => {
+                                ui.label("Download complete!");
+                            }
+                            AppState::Initial => (),
+                            AppState::Downloading => {
+                                ui.add(
+                                    egui::ProgressBar::new(job.progress.fraction)
+                                        .animate(true)
+                                        .text(progress_label(&job.progress)),
+                                );
+                            }
+                            AppState::Failed { message, retryable } => {
+                                ui.colored_label(egui::Color32::RED, message);
+
+                                if *retryable && ui.button("Retry").clicked() {
+                                    job.state = AppState::Initial;
+                                    jobs_to_retry.push(Arc::clone(job_arc));
+                                }
+                            }
+                        };
                     });
                 }
-            }
+            });
 
-            match state_val {
-                AppState::Done => {
-                    ui.label("Download complete!");
-                }
-                AppState::Initial => (),
-                AppState::Downloading => {
-                    ui.label("Downloading...");
-                    ui.add(
-                        egui::ProgressBar::new(*value.lock().unwrap())
-                            .animate(true)
-                            .show_percentage(),
-                    );
-                }
-            };
+            for job in jobs_to_retry {
+                self.start_job(job, ctx);
+            }
 
             egui::warn_if_debug_build(ui);
         });